@@ -1,10 +1,11 @@
 use clap::{App, Arg, SubCommand};
 use rand::Rng;
+use sha3::{Digest, Sha3_256};
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, read_keypair_file};
 use solana_sdk::signature::Signer;
-use std::{error::Error, str::FromStr};
-use vrf_program::{GameState, VrfClient};
+use std::{error::Error, str::FromStr, thread, time::Duration};
+use vrf_program::{GameState, Vrf, VrfClient};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let matches = App::new("VRF Game CLI")
@@ -31,6 +32,85 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .help("The program ID of the deployed VRF program")
                         .takes_value(true)
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("oracle_keypair")
+                        .short("o")
+                        .long("oracle-keypair")
+                        .value_name("KEYPAIR")
+                        .help("Path to the oracle's keypair file; its public key is registered as the VRF signer")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("oracle")
+                .about("Watch a VRF account and fulfill its pending randomness request")
+                .arg(
+                    Arg::with_name("keypair")
+                        .short("k")
+                        .long("keypair")
+                        .value_name("KEYPAIR")
+                        .help("Path to the oracle's keypair file (must match the registered public key)")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("program_id")
+                        .short("p")
+                        .long("program-id")
+                        .value_name("PUBKEY")
+                        .help("The program ID of the deployed VRF program")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("vrf_account")
+                        .short("v")
+                        .long("vrf-account")
+                        .value_name("PUBKEY")
+                        .help("The VRF account pubkey")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("game_account")
+                        .short("g")
+                        .long("game-account")
+                        .value_name("PUBKEY")
+                        .help("The game account pubkey")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("crank")
+                .about("Run a background oracle crank, fulfilling every pending round registered to this oracle")
+                .arg(
+                    Arg::with_name("keypair")
+                        .short("k")
+                        .long("keypair")
+                        .value_name("KEYPAIR")
+                        .help("Path to the oracle's keypair file (must match the registered public key)")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("program_id")
+                        .short("p")
+                        .long("program-id")
+                        .value_name("PUBKEY")
+                        .help("The program ID of the deployed VRF program")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("poll_interval_ms")
+                        .long("poll-interval-ms")
+                        .value_name("MILLISECONDS")
+                        .help("How often to scan for pending rounds")
+                        .takes_value(true)
+                        .default_value("2000"),
                 ),
         )
         .subcommand(
@@ -93,26 +173,82 @@ fn main() -> Result<(), Box<dyn Error>> {
             .map_err(|_| format!("Failed to read keypair from {}", keypair_path))?;
         
         let program_id = Pubkey::from_str(matches.value_of("program_id").unwrap())?;
-        
+
+        let oracle_keypair_path = matches.value_of("oracle_keypair").unwrap();
+        let oracle_keypair = read_keypair_file(oracle_keypair_path)
+            .map_err(|_| format!("Failed to read oracle keypair from {}", oracle_keypair_path))?;
+
         let client = VrfClient::new(url, payer, program_id);
-        
+
         // Generate new keypairs for VRF and game accounts
         let vrf_account = Keypair::new();
         let game_account = Keypair::new();
-        
+
         println!("Initializing VRF account: {}", vrf_account.pubkey());
-        let signature = client.initialize_vrf(&vrf_account)?;
+        let signature = client.initialize_vrf(&vrf_account, oracle_keypair.pubkey().to_bytes(), &game_account.pubkey())?;
         println!("VRF account initialized. Signature: {}", signature);
+        println!("Registered oracle: {}", oracle_keypair.pubkey());
         
         println!("Initializing game account: {}", game_account.pubkey());
         let signature = client.initialize_game(&game_account)?;
         println!("Game account initialized. Signature: {}", signature);
-        
+
         println!("\nAccounts created successfully!");
         println!("VRF Account: {}", vrf_account.pubkey());
         println!("Game Account: {}", game_account.pubkey());
-        println!("\nUse these account addresses with the 'play' command to start playing.");
-        
+        println!("\nUse these account addresses with the 'play' command to start playing, and keep");
+        println!("the 'oracle' command running against them with the oracle keypair to fulfill randomness.");
+
+    } else if let Some(matches) = matches.subcommand_matches("oracle") {
+        let keypair_path = matches.value_of("keypair").unwrap();
+        let payer = read_keypair_file(keypair_path)
+            .map_err(|_| format!("Failed to read keypair from {}", keypair_path))?;
+        let oracle_signer = read_keypair_file(keypair_path)
+            .map_err(|_| format!("Failed to read keypair from {}", keypair_path))?;
+
+        let program_id = Pubkey::from_str(matches.value_of("program_id").unwrap())?;
+        let vrf_account = Pubkey::from_str(matches.value_of("vrf_account").unwrap())?;
+        let game_account = Pubkey::from_str(matches.value_of("game_account").unwrap())?;
+
+        let client = VrfClient::new(url, payer, program_id);
+        let secret_seed: [u8; 32] = oracle_signer.to_bytes()[0..32].try_into().unwrap();
+
+        println!("Watching VRF account {} for a pending request...", vrf_account);
+        loop {
+            let vrf_data = client.get_vrf_account_data(&vrf_account)?;
+            let round_index = vrf_data.counter as usize;
+            match vrf_data.rounds.get(round_index) {
+                None => {}
+                Some(round) if round.randomness.is_some() => {
+                    println!("Round {} already fulfilled; waiting for the next request.", round_index);
+                }
+                Some(round) => {
+                    println!("Found pending request for round {}, computing VRF proof...", round_index);
+                    let proof = Vrf::prove(&secret_seed, &round.seed)
+                        .map_err(|e| format!("failed to compute VRF proof: {:?}", e))?;
+                    let signature = client.fulfill_randomness(&oracle_signer, &vrf_account, &game_account, proof, None)?;
+                    println!("Round {} randomness fulfilled. Signature: {}", round_index, signature);
+                    break;
+                }
+            }
+            thread::sleep(Duration::from_secs(2));
+        }
+
+    } else if let Some(matches) = matches.subcommand_matches("crank") {
+        let keypair_path = matches.value_of("keypair").unwrap();
+        let payer = read_keypair_file(keypair_path)
+            .map_err(|_| format!("Failed to read keypair from {}", keypair_path))?;
+        let oracle_signer = read_keypair_file(keypair_path)
+            .map_err(|_| format!("Failed to read keypair from {}", keypair_path))?;
+
+        let program_id = Pubkey::from_str(matches.value_of("program_id").unwrap())?;
+        let poll_interval_ms = matches.value_of("poll_interval_ms").unwrap().parse::<u64>()?;
+
+        let client = VrfClient::new(url, payer, program_id);
+
+        println!("Running oracle crank for {}, polling every {}ms...", oracle_signer.pubkey(), poll_interval_ms);
+        client.run_crank(&oracle_signer, Duration::from_millis(poll_interval_ms))?;
+
     } else if let Some(matches) = matches.subcommand_matches("play") {
         let keypair_path = matches.value_of("keypair").unwrap();
         let payer = read_keypair_file(keypair_path)
@@ -128,44 +264,55 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         
         let client = VrfClient::new(url, payer, program_id);
-        
-        // Generate a random seed
         let mut rng = rand::thread_rng();
+
+        let game_data = client.get_game_account_data(&game_account)?;
+        let round = game_data.rounds.len() as u8;
+
+        // Commit to the guess before requesting randomness, so the winning
+        // number can't be read on-chain and then guessed.
+        let mut blinding = [0u8; 32];
+        rng.fill(&mut blinding);
+        let mut hasher = Sha3_256::new();
+        hasher.update([guess]);
+        hasher.update(client.payer.pubkey().as_ref());
+        hasher.update(blinding);
+        let commitment: [u8; 32] = hasher.finalize().into();
+
+        println!("Committing to guess for round {}...", round);
+        let signature = client.commit_guess(&client.payer, &game_account, round, commitment)?;
+        println!("Commitment recorded. Signature: {}", signature);
+
+        // Generate a random seed
         let seed: Vec<u8> = (0..32).map(|_| rng.gen::<u8>()).collect();
-        
+
         println!("Requesting randomness with seed: {:?}", seed);
         let signature = client.request_randomness(&vrf_account, &game_account, seed.clone())?;
         println!("Randomness requested. Signature: {}", signature);
-        
-        // In a real VRF implementation, this would be done by an oracle or some other entity
-        // that has the secret key. For our demo, we're doing it in the client.
-        println!("Fulfilling randomness with dummy proof...");
-        let dummy_proof = [1u8; 64]; // In a real implementation, this would be a valid VRF proof
-        let signature = client.fulfill_randomness(&vrf_account, &game_account, dummy_proof)?;
-        println!("Randomness fulfilled. Signature: {}", signature);
-        
-        // Wait for the game state to be updated
-        println!("Waiting for game state to be ready for player guess...");
-        client.wait_for_game_state(&game_account, GameState::AwaitingPlayerGuess)?;
-        
+
+        // Fulfillment is the oracle's job now (run the `oracle` subcommand
+        // against this VRF account), not something the player's client does.
+        println!("Waiting for the oracle to fulfill randomness...");
+        client.wait_for_game_state(&game_account, round, GameState::AwaitingPlayerGuess)?;
+
         println!("Submitting guess: {}", guess);
-        let signature = client.submit_guess(&client.payer, &game_account, &vrf_account, guess)?;
+        let signature = client.submit_guess(&client.payer, &game_account, &vrf_account, round, guess, blinding)?;
         println!("Guess submitted. Signature: {}", signature);
-        
+
         // Wait for the game to complete and get the result
         println!("Waiting for game to complete...");
-        let final_state = client.wait_for_game_state(&game_account, GameState::GameComplete)?;
-        
-        match final_state.result {
+        let final_state = client.wait_for_game_state(&game_account, round, GameState::GameComplete)?;
+
+        match final_state.rounds.get(round as usize).and_then(|r| r.result.clone()) {
             Some(result) => {
                 match result {
                     vrf_program::GameResult::Win => println!("Congratulations! You won!"),
                     vrf_program::GameResult::Lose => println!("Sorry, you lost. Better luck next time!"),
                 }
-                
+
                 // Get the VRF account data to see the randomness
                 let vrf_data = client.get_vrf_account_data(&vrf_account)?;
-                if let Some(randomness) = vrf_data.randomness {
+                if let Some(randomness) = vrf_data.rounds.get(round as usize).and_then(|r| r.randomness) {
                     let game_number = vrf_program::calculate_game_number(&randomness);
                     println!("The winning number was: {}", game_number);
                 }
@@ -17,6 +17,24 @@ pub enum VrfError {
     
     #[error("Randomness not yet available")]
     RandomnessNotAvailable,
+
+    #[error("Revealed guess does not match the recorded commitment")]
+    CommitmentMismatch,
+
+    #[error("The same account was passed in more than one account slot")]
+    DuplicateAccount,
+
+    #[error("Account is too small to hold the expected state")]
+    AccountTooSmall,
+
+    #[error("Account is not rent-exempt")]
+    NotRentExempt,
+
+    #[error("Requested more rounds than the account has capacity for")]
+    TooManyRounds,
+
+    #[error("The round's witness condition was not satisfied")]
+    WitnessConditionNotMet,
 }
 
 impl From<VrfError> for ProgramError {
@@ -1,14 +1,29 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+
+use crate::state::WitnessCondition;
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub enum VrfInstruction {
-    /// Initialize a new VRF account
+    /// Initialize a new VRF account, registering the oracle's real ed25519
+    /// public key so fulfillments can be verified against it instead of a
+    /// placeholder, along with the game account its rounds will feed into
+    /// so an oracle crank can discover the pairing on-chain.
     /// Accounts expected:
     /// 0. `[signer, writable]` Authority account that will manage the VRF
     /// 1. `[writable]` The VRF account to initialize
-    Initialize,
+    /// 2. `[]` Rent sysvar
+    Initialize {
+        public_key: [u8; 32],
+        game_account: Pubkey,
+    },
     
-    /// Request randomness with a seed
+    /// Request randomness for a single round. Equivalent to
+    /// `RequestRandomnessBatch` with one seed.
     /// Accounts expected:
     /// 0. `[signer]` Authority account
     /// 1. `[writable]` The VRF account
@@ -16,14 +31,46 @@ pub enum VrfInstruction {
     RequestRandomness {
         seed: Vec<u8>,
     },
-    
-    /// Fulfill randomness with proof
+
+    /// Request randomness for several rounds at once, so a single
+    /// initialized account pair can run many guessing rounds without
+    /// re-initialization. Each seed starts a new round appended after the
+    /// account's existing rounds; the player must already have a matching
+    /// `CommitGuess` recorded for each of those rounds.
     /// Accounts expected:
     /// 0. `[signer]` Authority account
     /// 1. `[writable]` The VRF account
     /// 2. `[writable]` Game account that will use the randomness
+    RequestRandomnessBatch {
+        seeds: Vec<[u8; 32]>,
+        rounds: u8,
+    },
+
+    /// Request randomness for several rounds at once, each gated on a
+    /// `WitnessCondition` that `FulfillRandomness` must check before it's
+    /// allowed to apply that round's proof. `conditions[i]` pairs with
+    /// `seeds[i]`; pass `None` for a round with no condition.
+    /// Accounts expected: same as `RequestRandomnessBatch`.
+    RequestRandomnessBatchWithConditions {
+        seeds: Vec<[u8; 32]>,
+        rounds: u8,
+        conditions: Vec<Option<WitnessCondition>>,
+    },
+
+    /// Fulfill randomness for the next pending round (`VrfAccountState::counter`).
+    /// Accounts expected:
+    /// 0. `[signer]` Oracle authority account (the registered `public_key`'s owner,
+    ///    distinct from the requesting `authority`)
+    /// 1. `[writable]` The VRF account
+    /// 2. `[writable]` Game account that will use the randomness
+    /// 3. `[]` Witness account, required only when the round's `condition`
+    ///    is `Some(WitnessCondition::AccountData { .. })`: the account the
+    ///    condition names. A `Timestamp` condition is checked against the
+    ///    runtime clock and needs no witness account.
     FulfillRandomness {
-        proof: [u8; 64],
+        /// `pi = (Gamma‖c‖s)`: 32-byte compressed Edwards point, 16-byte
+        /// challenge, 32-byte scalar (RFC 9381 ECVRF-EDWARDS25519-SHA512-TAI).
+        proof: [u8; 80],
     },
 }
 
@@ -33,14 +80,224 @@ pub enum GameInstruction {
     /// Accounts expected:
     /// 0. `[signer, writable]` Authority account
     /// 1. `[writable]` The game account to initialize
+    /// 2. `[]` Rent sysvar
     InitializeGame,
-    
-    /// Submit a player's guess
+
+    /// Commit to a guess for the next round before randomness is requested
+    /// for it, so the winning number can't be read off-chain and then guessed.
+    /// Accounts expected:
+    /// 0. `[signer]` Player account
+    /// 1. `[writable]` The game account
+    CommitGuess {
+        round: u8,
+        commitment: [u8; 32],
+    },
+
+    /// Reveal the guess committed to for `round` and score it against that
+    /// round's fulfilled randomness.
     /// Accounts expected:
     /// 0. `[signer]` Player account
     /// 1. `[writable]` The game account
     /// 2. `[]` The VRF account (readonly)
     SubmitGuess {
+        round: u8,
         guess: u8,
+        blinding: [u8; 32],
     },
-} 
\ No newline at end of file
+}
+
+/// Reserved leading dispatch bytes for `process_instruction` (see `lib.rs`).
+/// Needed because `VrfInstruction`'s own Borsh-derived tag starts at 0, which
+/// collides with `GAME_INSTRUCTION_TAG` — these two bytes, not the enums'
+/// own encodings, are what the entrypoint actually routes on.
+const GAME_INSTRUCTION_TAG: u8 = 0;
+const VRF_INSTRUCTION_TAG: u8 = 1;
+
+/// `GameInstruction`s are dispatched through `process_instruction` behind a
+/// leading `GAME_INSTRUCTION_TAG` byte (see `lib.rs`), so every builder here
+/// prepends it ahead of the variant's own Borsh encoding.
+fn game_instruction_data(instruction: &GameInstruction) -> Vec<u8> {
+    let mut data = vec![GAME_INSTRUCTION_TAG];
+    data.extend(instruction.try_to_vec().expect("instruction serialization failed"));
+    data
+}
+
+/// `VrfInstruction`s are dispatched through `process_instruction` behind a
+/// leading `VRF_INSTRUCTION_TAG` byte (see `lib.rs`), so every builder here
+/// prepends it ahead of the variant's own Borsh encoding. Without this, a
+/// `VrfInstruction::Initialize` (Borsh tag `0`) would be indistinguishable
+/// on the wire from a `GameInstruction`.
+fn vrf_instruction_data(instruction: &VrfInstruction) -> Vec<u8> {
+    let mut data = vec![VRF_INSTRUCTION_TAG];
+    data.extend(instruction.try_to_vec().expect("instruction serialization failed"));
+    data
+}
+
+/// Creates an `Initialize` instruction, registering the oracle's public key
+/// and the game account its rounds will feed into.
+pub fn initialize(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    vrf_account: &Pubkey,
+    public_key: [u8; 32],
+    game_account: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &vrf_instruction_data(&VrfInstruction::Initialize { public_key, game_account }),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*vrf_account, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+/// Creates a `RequestRandomness` instruction for a single round.
+pub fn request_randomness(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    vrf_account: &Pubkey,
+    game_account: &Pubkey,
+    seed: Vec<u8>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &vrf_instruction_data(&VrfInstruction::RequestRandomness { seed }),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*vrf_account, false),
+            AccountMeta::new(*game_account, false),
+        ],
+    )
+}
+
+/// Creates a `RequestRandomnessBatch` instruction for several rounds at once.
+pub fn request_randomness_batch(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    vrf_account: &Pubkey,
+    game_account: &Pubkey,
+    seeds: Vec<[u8; 32]>,
+) -> Instruction {
+    let rounds = seeds.len() as u8;
+    Instruction::new_with_bytes(
+        *program_id,
+        &vrf_instruction_data(&VrfInstruction::RequestRandomnessBatch { seeds, rounds }),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*vrf_account, false),
+            AccountMeta::new(*game_account, false),
+        ],
+    )
+}
+
+/// Creates a `RequestRandomnessBatchWithConditions` instruction. `conditions`
+/// must be the same length as `seeds`.
+pub fn request_randomness_batch_with_conditions(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    vrf_account: &Pubkey,
+    game_account: &Pubkey,
+    seeds: Vec<[u8; 32]>,
+    conditions: Vec<Option<WitnessCondition>>,
+) -> Instruction {
+    let rounds = seeds.len() as u8;
+    Instruction::new_with_bytes(
+        *program_id,
+        &vrf_instruction_data(&VrfInstruction::RequestRandomnessBatchWithConditions {
+            seeds,
+            rounds,
+            conditions,
+        }),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*vrf_account, false),
+            AccountMeta::new(*game_account, false),
+        ],
+    )
+}
+
+/// Creates a `FulfillRandomness` instruction, signed by the registered
+/// oracle. `witness_account` must be supplied (the account an `AccountData`
+/// condition names) when the round being fulfilled has one; a `Timestamp`
+/// condition or no condition at all needs no witness account.
+pub fn fulfill_randomness(
+    program_id: &Pubkey,
+    oracle: &Pubkey,
+    vrf_account: &Pubkey,
+    game_account: &Pubkey,
+    proof: [u8; 80],
+    witness_account: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*oracle, true),
+        AccountMeta::new(*vrf_account, false),
+        AccountMeta::new(*game_account, false),
+    ];
+    if let Some(witness_account) = witness_account {
+        accounts.push(AccountMeta::new_readonly(witness_account, false));
+    }
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &vrf_instruction_data(&VrfInstruction::FulfillRandomness { proof }),
+        accounts,
+    )
+}
+
+/// Creates an `InitializeGame` instruction.
+pub fn initialize_game(program_id: &Pubkey, authority: &Pubkey, game_account: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &game_instruction_data(&GameInstruction::InitializeGame),
+        vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*game_account, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+/// Creates a `CommitGuess` instruction for `round`.
+pub fn commit_guess(
+    program_id: &Pubkey,
+    player: &Pubkey,
+    game_account: &Pubkey,
+    round: u8,
+    commitment: [u8; 32],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &game_instruction_data(&GameInstruction::CommitGuess { round, commitment }),
+        vec![
+            AccountMeta::new(*player, true),
+            AccountMeta::new(*game_account, false),
+        ],
+    )
+}
+
+/// Creates a `SubmitGuess` instruction for `round`.
+pub fn submit_guess(
+    program_id: &Pubkey,
+    player: &Pubkey,
+    game_account: &Pubkey,
+    vrf_account: &Pubkey,
+    round: u8,
+    guess: u8,
+    blinding: [u8; 32],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &game_instruction_data(&GameInstruction::SubmitGuess {
+            round,
+            guess,
+            blinding,
+        }),
+        vec![
+            AccountMeta::new(*player, true),
+            AccountMeta::new(*game_account, false),
+            AccountMeta::new_readonly(*vrf_account, false),
+        ],
+    )
+}
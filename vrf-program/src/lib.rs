@@ -1,3 +1,8 @@
+// `entrypoint!`'s expansion (invoked below) checks cfgs this crate doesn't
+// declare in its own Cargo.toml; that's a mismatch in solana_program's own
+// check-cfg metadata, not something our code controls.
+#![allow(unexpected_cfgs)]
+
 pub mod client;
 pub mod error;
 pub mod instruction;
@@ -8,7 +13,10 @@ pub mod vrf;
 pub use crate::error::VrfError;
 pub use crate::instruction::{GameInstruction, VrfInstruction};
 pub use crate::processor::{process_game_instruction, process_instruction as processor_process_instruction};
-pub use crate::state::{calculate_game_number, GameAccountState, GameResult, GameState, VrfAccountState};
+pub use crate::state::{
+    calculate_game_number, GameAccountState, GameResult, GameRound, GameState, VrfAccountState,
+    VrfRound, WitnessCondition, MAX_ROUNDS,
+};
 pub use crate::vrf::Vrf;
 pub use crate::client::VrfClient;
 
@@ -28,11 +36,13 @@ pub fn process_instruction(
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    if instruction_data.len() > 0 && instruction_data[0] == 0 {
-        // If the first byte is 0, process as a game instruction
-        processor::process_game_instruction(program_id, accounts, &instruction_data[1..])
-    } else {
-        // Otherwise, process as a VRF instruction
-        processor::process_instruction(program_id, accounts, instruction_data)
+    // The leading byte is a dispatch tag reserved by `instruction.rs`
+    // (`GAME_INSTRUCTION_TAG` / `VRF_INSTRUCTION_TAG`), not part of either
+    // enum's own Borsh encoding — `VrfInstruction::Initialize`'s Borsh tag is
+    // also 0, so routing on the enums' raw encoding would be ambiguous.
+    match instruction_data.split_first() {
+        Some((0, rest)) => processor::process_game_instruction(program_id, accounts, rest),
+        Some((1, rest)) => processor::process_instruction(program_id, accounts, rest),
+        _ => Err(solana_program::program_error::ProgramError::InvalidInstructionData),
     }
 }
@@ -2,20 +2,53 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use sha3::{Digest, Sha3_256};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
 };
 
 use crate::{
     error::VrfError,
     instruction::{GameInstruction, VrfInstruction},
     state::{
-        calculate_game_number, GameAccountState, GameResult, GameState, VrfAccountState,
+        calculate_game_number, GameAccountState, GameResult, GameRound, GameState,
+        VrfAccountState, VrfRound, WitnessCondition, MAX_ROUNDS,
     },
+    vrf::Vrf,
 };
 
+// Reject handlers that were passed the same account in two different
+// slots: each one independently `borrow_mut()`s its slot, so an aliased
+// account would panic on the second borrow (or silently let one write
+// clobber the other).
+fn check_distinct_accounts(accounts: &[&AccountInfo]) -> ProgramResult {
+    for i in 0..accounts.len() {
+        for j in (i + 1)..accounts.len() {
+            if accounts[i].key == accounts[j].key {
+                return Err(VrfError::DuplicateAccount.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+// A too-small or non-rent-exempt account will fail Borsh serialization or
+// get garbage-collected mid-game, so every init handler checks both before
+// writing state.
+fn check_rent_exempt_and_sized(account: &AccountInfo, rent: &Rent, required_len: usize) -> ProgramResult {
+    if account.data_len() < required_len {
+        return Err(VrfError::AccountTooSmall.into());
+    }
+    if !rent.is_exempt(account.lamports(), account.data_len()) {
+        return Err(VrfError::NotRentExempt.into());
+    }
+    Ok(())
+}
+
 // Program logic
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -26,17 +59,48 @@ pub fn process_instruction(
         .map_err(|_| VrfError::InvalidInstruction)?;
 
     match instruction {
-        VrfInstruction::Initialize => initialize_vrf(program_id, accounts),
-        VrfInstruction::RequestRandomness { seed } => request_randomness(program_id, accounts, seed),
+        VrfInstruction::Initialize { public_key, game_account } => {
+            initialize_vrf(program_id, accounts, public_key, game_account)
+        }
+        VrfInstruction::RequestRandomness { seed } => {
+            let seed: [u8; 32] = seed
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+            request_randomness(program_id, accounts, vec![seed], vec![None])
+        }
+        VrfInstruction::RequestRandomnessBatch { seeds, rounds } => {
+            if seeds.len() != rounds as usize {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            let conditions = vec![None; seeds.len()];
+            request_randomness(program_id, accounts, seeds, conditions)
+        }
+        VrfInstruction::RequestRandomnessBatchWithConditions {
+            seeds,
+            rounds,
+            conditions,
+        } => {
+            if seeds.len() != rounds as usize || seeds.len() != conditions.len() {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            request_randomness(program_id, accounts, seeds, conditions)
+        }
         VrfInstruction::FulfillRandomness { proof } => fulfill_randomness(program_id, accounts, proof),
     }
 }
 
-// Initialize a new VRF account
-fn initialize_vrf(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+// Initialize a new VRF account, registering the oracle's public key
+fn initialize_vrf(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    public_key: [u8; 32],
+    game_account: Pubkey,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let authority = next_account_info(account_info_iter)?;
     let vrf_account = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    check_distinct_accounts(&[authority, vrf_account])?;
 
     if !authority.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
@@ -46,17 +110,19 @@ fn initialize_vrf(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResul
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    // Generate VRF key pair (in a real implementation, the authority should provide this)
-    // Here we're using a dummy key for simplicity
-    let public_key = [1u8; 32]; // In a real implementation, this would be a proper ed25519 public key
+    let rent = Rent::from_account_info(rent_sysvar)?;
+    check_rent_exempt_and_sized(vrf_account, &rent, VrfAccountState::LEN)?;
+
+    let oracle_authority = Pubkey::new_from_array(public_key);
 
     let vrf_state = VrfAccountState {
         authority: *authority.key,
         is_initialized: true,
-        seed: None,
-        randomness: None,
-        proof: None,
         public_key,
+        oracle_authority,
+        game_account,
+        counter: 0,
+        rounds: Vec::new(),
     };
 
     vrf_state.serialize(&mut *vrf_account.data.borrow_mut())?;
@@ -64,12 +130,20 @@ fn initialize_vrf(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResul
     Ok(())
 }
 
-// Request randomness with a seed
-fn request_randomness(program_id: &Pubkey, accounts: &[AccountInfo], seed: Vec<u8>) -> ProgramResult {
+// Request randomness for one or more rounds. A plain `RequestRandomness` is
+// just this with a single seed; `RequestRandomnessBatch` passes several at
+// once so many rounds can be played without re-initializing the accounts.
+fn request_randomness(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    seeds: Vec<[u8; 32]>,
+    conditions: Vec<Option<WitnessCondition>>,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let authority = next_account_info(account_info_iter)?;
     let vrf_account = next_account_info(account_info_iter)?;
     let game_account = next_account_info(account_info_iter)?;
+    check_distinct_accounts(&[authority, vrf_account, game_account])?;
 
     if !authority.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
@@ -80,51 +154,83 @@ fn request_randomness(program_id: &Pubkey, accounts: &[AccountInfo], seed: Vec<u
     }
 
     let mut vrf_state = VrfAccountState::try_from_slice(&vrf_account.data.borrow())?;
-    
+
     if vrf_state.authority != *authority.key {
         return Err(VrfError::InvalidAuthority.into());
     }
 
-    // Initialize or validate game account
-    let mut game_state = if game_account.data_is_empty() {
-        GameAccountState {
-            authority: *authority.key,
-            is_initialized: true,
-            randomness: None,
-            game_state: GameState::AwaitingRandomness,
-            player_guess: None,
-            result: None,
-        }
-    } else {
-        GameAccountState::try_from_slice(&game_account.data.borrow())?
-    };
+    // `game_account` must be the one this VRF account was paired with at
+    // `Initialize` time, or anyone could point a self-controlled VRF account
+    // at someone else's live game and write arbitrary randomness into it.
+    if vrf_state.game_account != *game_account.key {
+        return Err(VrfError::InvalidAuthority.into());
+    }
 
-    // Set the seed for VRF
-    vrf_state.seed = Some(seed);
-    vrf_state.randomness = None;
-    vrf_state.proof = None;
+    if vrf_state.rounds.len() + seeds.len() > MAX_ROUNDS {
+        return Err(VrfError::TooManyRounds.into());
+    }
 
-    // Update game state
-    game_state.game_state = GameState::AwaitingRandomness;
-    game_state.randomness = None;
-    game_state.player_guess = None;
-    game_state.result = None;
+    // Every round being requested must already have a matching commitment
+    // recorded, or the player could see the seed and pick their guess to match.
+    let game_state = GameAccountState::try_from_slice(&game_account.data.borrow())?;
+    if vrf_state.authority != game_state.authority {
+        return Err(VrfError::InvalidAuthority.into());
+    }
+    if game_state.rounds.len() < vrf_state.rounds.len() + seeds.len() {
+        return Err(VrfError::InvalidGameState.into());
+    }
+    for round in &game_state.rounds[vrf_state.rounds.len()..vrf_state.rounds.len() + seeds.len()] {
+        if round.commitment.is_none() {
+            return Err(VrfError::InvalidGameState.into());
+        }
+    }
+
+    let requested = seeds.len();
+    for (seed, condition) in seeds.into_iter().zip(conditions) {
+        vrf_state.rounds.push(VrfRound {
+            seed: seed.to_vec(),
+            randomness: None,
+            proof: None,
+            condition,
+        });
+    }
 
     vrf_state.serialize(&mut *vrf_account.data.borrow_mut())?;
-    game_state.serialize(&mut *game_account.data.borrow_mut())?;
 
-    msg!("Randomness requested");
+    msg!("Randomness requested for {} round(s)", requested);
     Ok(())
 }
 
-// Fulfill randomness with proof
-fn fulfill_randomness(program_id: &Pubkey, accounts: &[AccountInfo], proof: [u8; 64]) -> ProgramResult {
+// Check that the witness account named by an `AccountData` condition is the
+// right account, owned by the right program, and hashes to the right value.
+// Takes no part in `Timestamp`, which is checked against the runtime clock
+// instead of a caller-supplied account (see `fulfill_randomness`).
+fn check_account_data_witness(
+    pubkey: &Pubkey,
+    owner: &Pubkey,
+    expected_hash: &[u8; 32],
+    witness_account: &AccountInfo,
+) -> ProgramResult {
+    if witness_account.key != pubkey || witness_account.owner != owner {
+        return Err(VrfError::WitnessConditionNotMet.into());
+    }
+    let mut hasher = Sha3_256::new();
+    hasher.update(&*witness_account.data.borrow());
+    let actual_hash = hasher.finalize();
+    if actual_hash.as_slice() != expected_hash {
+        return Err(VrfError::WitnessConditionNotMet.into());
+    }
+    Ok(())
+}
+
+// Fulfill randomness for the next pending round (`vrf_state.counter`)
+fn fulfill_randomness(program_id: &Pubkey, accounts: &[AccountInfo], proof: [u8; 80]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let authority = next_account_info(account_info_iter)?;
+    let oracle = next_account_info(account_info_iter)?;
     let vrf_account = next_account_info(account_info_iter)?;
     let game_account = next_account_info(account_info_iter)?;
 
-    if !authority.is_signer {
+    if !oracle.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
@@ -133,37 +239,72 @@ fn fulfill_randomness(program_id: &Pubkey, accounts: &[AccountInfo], proof: [u8;
     }
 
     let mut vrf_state = VrfAccountState::try_from_slice(&vrf_account.data.borrow())?;
-    
-    if vrf_state.authority != *authority.key {
+
+    // Fulfillment is gated on the registered oracle, not the requesting
+    // authority, so the party running the game can't also forge randomness.
+    if vrf_state.oracle_authority != *oracle.key || vrf_state.authority == *oracle.key {
+        return Err(VrfError::InvalidAuthority.into());
+    }
+
+    // `game_account` must be the one this VRF account was paired with at
+    // `Initialize` time, or a self-controlled VRF account (attacker knows its
+    // own oracle secret key, so its own proofs verify) could be pointed at
+    // someone else's live game and clobber its randomness outright.
+    if vrf_state.game_account != *game_account.key {
         return Err(VrfError::InvalidAuthority.into());
     }
 
-    // Verify the VRF proof (simplified for this example)
-    // In a real implementation, we would verify the proof cryptographically
-    let seed = vrf_state.seed.as_ref().ok_or(ProgramError::InvalidArgument)?;
-    
-    // For this example, we'll generate randomness by hashing the proof and seed
-    let mut hasher = Sha3_256::new();
-    hasher.update(&proof);
-    hasher.update(seed);
-    let randomness = hasher.finalize();
-    
-    let mut randomness_bytes = [0u8; 32];
-    randomness_bytes.copy_from_slice(&randomness);
-
-    // Update VRF state
-    vrf_state.proof = Some(proof);
-    vrf_state.randomness = Some(randomness_bytes);
-
-    // Update game state
     let mut game_state = GameAccountState::try_from_slice(&game_account.data.borrow())?;
-    game_state.randomness = Some(randomness_bytes);
-    game_state.game_state = GameState::AwaitingPlayerGuess;
+    if vrf_state.authority != game_state.authority {
+        return Err(VrfError::InvalidAuthority.into());
+    }
+
+    let round_index = vrf_state.counter as usize;
+    let round = vrf_state
+        .rounds
+        .get(round_index)
+        .ok_or(VrfError::RandomnessNotAvailable)?;
+
+    // `AccountData` conditions need a 4th witness account, checked before the
+    // proof is applied; `Timestamp` is checked against the runtime clock, and
+    // neither it nor an unconditioned round reads a witness account (any
+    // extra account passed is ignored).
+    match &round.condition {
+        None => check_distinct_accounts(&[oracle, vrf_account, game_account])?,
+        Some(WitnessCondition::Timestamp { not_before }) => {
+            check_distinct_accounts(&[oracle, vrf_account, game_account])?;
+            if Clock::get()?.unix_timestamp < *not_before {
+                return Err(VrfError::WitnessConditionNotMet.into());
+            }
+        }
+        Some(WitnessCondition::AccountData { pubkey, program_id: owner, expected_hash }) => {
+            let witness_account = next_account_info(account_info_iter)?;
+            check_distinct_accounts(&[oracle, vrf_account, game_account, witness_account])?;
+            check_account_data_witness(pubkey, owner, expected_hash, witness_account)?;
+        }
+    }
+
+    // Verify the ECVRF proof against the stored public key. `beta` becomes
+    // the randomness only if the proof actually opens under `vrf_state.public_key`.
+    let randomness_bytes = Vrf::verify(&vrf_state.public_key, &round.seed, &proof)?;
+
+    vrf_state.rounds[round_index].proof = Some(proof);
+    vrf_state.rounds[round_index].randomness = Some(randomness_bytes);
+    vrf_state.counter += 1;
+
+    let game_round = game_state
+        .rounds
+        .get_mut(round_index)
+        .ok_or(VrfError::InvalidGameState)?;
+    game_round.randomness = Some(randomness_bytes);
+    if round_index == game_state.current_round as usize {
+        game_state.game_state = GameState::AwaitingPlayerGuess;
+    }
 
     vrf_state.serialize(&mut *vrf_account.data.borrow_mut())?;
     game_state.serialize(&mut *game_account.data.borrow_mut())?;
 
-    msg!("Randomness fulfilled: {:?}", randomness_bytes);
+    msg!("Round {} randomness fulfilled: {:?}", round_index, randomness_bytes);
     Ok(())
 }
 
@@ -178,7 +319,12 @@ pub fn process_game_instruction(
 
     match instruction {
         GameInstruction::InitializeGame => initialize_game(program_id, accounts),
-        GameInstruction::SubmitGuess { guess } => submit_guess(program_id, accounts, guess),
+        GameInstruction::CommitGuess { round, commitment } => {
+            commit_guess(program_id, accounts, round, commitment)
+        }
+        GameInstruction::SubmitGuess { round, guess, blinding } => {
+            submit_guess(program_id, accounts, round, guess, blinding)
+        }
     }
 }
 
@@ -187,6 +333,8 @@ fn initialize_game(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResu
     let account_info_iter = &mut accounts.iter();
     let authority = next_account_info(account_info_iter)?;
     let game_account = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    check_distinct_accounts(&[authority, game_account])?;
 
     if !authority.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
@@ -196,26 +344,86 @@ fn initialize_game(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResu
         return Err(ProgramError::IncorrectProgramId);
     }
 
+    let rent = Rent::from_account_info(rent_sysvar)?;
+    check_rent_exempt_and_sized(game_account, &rent, GameAccountState::LEN)?;
+
     let game_state = GameAccountState {
         authority: *authority.key,
         is_initialized: true,
+        game_state: GameState::AwaitingCommitment,
+        current_round: 0,
+        rounds: Vec::new(),
+    };
+
+    game_state.serialize(&mut *game_account.data.borrow_mut())?;
+    msg!("Game account initialized");
+    Ok(())
+}
+
+// Record a player's blinded guess commitment for the next round before
+// randomness is requested for it
+fn commit_guess(program_id: &Pubkey, accounts: &[AccountInfo], round: u8, commitment: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let player = next_account_info(account_info_iter)?;
+    let game_account = next_account_info(account_info_iter)?;
+    check_distinct_accounts(&[player, game_account])?;
+
+    if !player.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if game_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut game_state = GameAccountState::try_from_slice(&game_account.data.borrow())?;
+
+    // Only the game's own player may commit a guess, or anyone could
+    // front-run the legitimate player by squatting the next round slot with
+    // a commitment the real player can never reveal.
+    if game_state.authority != *player.key {
+        return Err(VrfError::InvalidAuthority.into());
+    }
+
+    // Rounds are committed in order: `round` must be exactly the next slot.
+    if round as usize != game_state.rounds.len() {
+        return Err(VrfError::InvalidGameState.into());
+    }
+    if game_state.rounds.len() >= MAX_ROUNDS {
+        return Err(VrfError::TooManyRounds.into());
+    }
+
+    game_state.rounds.push(GameRound {
+        commitment: Some(commitment),
         randomness: None,
-        game_state: GameState::AwaitingRandomness,
         player_guess: None,
         result: None,
-    };
+    });
+
+    // Committing the round `game_state` describes leaves it waiting on the oracle.
+    if round == game_state.current_round {
+        game_state.game_state = GameState::AwaitingRandomness;
+    }
 
     game_state.serialize(&mut *game_account.data.borrow_mut())?;
-    msg!("Game account initialized");
+
+    msg!("Guess commitment recorded for round {}", round);
     Ok(())
 }
 
-// Process a player's guess
-fn submit_guess(program_id: &Pubkey, accounts: &[AccountInfo], guess: u8) -> ProgramResult {
+// Process a player's revealed guess for `round`
+fn submit_guess(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    round: u8,
+    guess: u8,
+    blinding: [u8; 32],
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let player = next_account_info(account_info_iter)?;
     let game_account = next_account_info(account_info_iter)?;
     let _vrf_account = next_account_info(account_info_iter)?;
+    check_distinct_accounts(&[player, game_account, _vrf_account])?;
 
     if !player.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
@@ -225,36 +433,64 @@ fn submit_guess(program_id: &Pubkey, accounts: &[AccountInfo], guess: u8) -> Pro
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    // Get game state
     let mut game_state = GameAccountState::try_from_slice(&game_account.data.borrow())?;
-    
-    // Check game is in correct state
-    if game_state.game_state != GameState::AwaitingPlayerGuess {
+
+    if game_state.authority != *player.key {
+        return Err(VrfError::InvalidAuthority.into());
+    }
+
+    if round != game_state.current_round || game_state.game_state != GameState::AwaitingPlayerGuess {
         return Err(VrfError::InvalidGameState.into());
     }
-    
-    // Get randomness from game state
-    let randomness = game_state.randomness.ok_or(VrfError::RandomnessNotAvailable)?;
-    
-    // Calculate game number from randomness
+
+    let round_index = round as usize;
+    let game_round = game_state
+        .rounds
+        .get(round_index)
+        .ok_or(VrfError::InvalidGameState)?;
+
+    // Reveal: the guess must match what was committed to before randomness existed
+    let commitment = game_round.commitment.ok_or(VrfError::InvalidGameState)?;
+    let mut hasher = Sha3_256::new();
+    hasher.update([guess]);
+    hasher.update(player.key.as_ref());
+    hasher.update(blinding);
+    let recomputed = hasher.finalize();
+    if recomputed.as_slice() != commitment {
+        return Err(VrfError::CommitmentMismatch.into());
+    }
+
+    let randomness = game_round.randomness.ok_or(VrfError::RandomnessNotAvailable)?;
     let game_number = calculate_game_number(&randomness);
-    
-    // Determine result
+
     let result = if guess == game_number {
         GameResult::Win
     } else {
         GameResult::Lose
     };
-    
-    // Update game state
-    game_state.player_guess = Some(guess);
-    game_state.result = Some(result.clone());
-    game_state.game_state = GameState::GameComplete;
-    
+
+    game_state.rounds[round_index].player_guess = Some(guess);
+    game_state.rounds[round_index].result = Some(result.clone());
+
+    // Advance to the next round. If it's already been fulfilled (it was
+    // included in the same randomness batch), skip straight to awaiting a
+    // guess for it; if it's been committed but not yet fulfilled, the game
+    // isn't complete, it's just waiting on the oracle. Only report
+    // `GameComplete` once there's no further committed round at all.
+    game_state.current_round += 1;
+    let next_round = game_state.current_round as usize;
+    game_state.game_state = match game_state.rounds.get(next_round) {
+        Some(next) if next.randomness.is_some() => GameState::AwaitingPlayerGuess,
+        Some(_) => GameState::AwaitingRandomness,
+        None => GameState::GameComplete,
+    };
+
     game_state.serialize(&mut *game_account.data.borrow_mut())?;
-    
-    msg!("Game complete! Number was: {}, Player guessed: {}, Result: {:?}", 
-         game_number, guess, result);
-    
+
+    msg!(
+        "Round {} complete! Number was: {}, Player guessed: {}, Result: {:?}",
+        round, game_number, guess, result
+    );
+
     Ok(())
-} 
\ No newline at end of file
+}
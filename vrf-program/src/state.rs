@@ -1,28 +1,121 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 
+/// Upper bound on rounds a single account pair can hold. The accounts are
+/// sized for this at `initialize_vrf`/`initialize_game` time since this
+/// program never reallocs account data.
+pub const MAX_ROUNDS: usize = 16;
+
+/// A condition that must hold before `FulfillRandomness` is allowed to apply
+/// a round's proof, so the oracle can't reveal randomness before some
+/// external event the commit-reveal flow depends on.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum WitnessCondition {
+    /// The Clock sysvar's `unix_timestamp` must be at or after `not_before`.
+    /// Checked via `Clock::get()`, not a caller-supplied witness account.
+    Timestamp { not_before: i64 },
+    /// The witness account must be `pubkey`, owned by `program_id`, and its
+    /// data must hash (SHA3-256) to `expected_hash`.
+    AccountData {
+        pubkey: Pubkey,
+        program_id: Pubkey,
+        expected_hash: [u8; 32],
+    },
+}
+
+/// One VRF round: the seed it was requested with, the proof/randomness once
+/// the oracle fulfills it, and an optional condition gating fulfillment.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct VrfRound {
+    pub seed: Vec<u8>,
+    pub randomness: Option<[u8; 32]>,
+    pub proof: Option<[u8; 80]>,
+    pub condition: Option<WitnessCondition>,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct VrfAccountState {
     pub authority: Pubkey,
     pub is_initialized: bool,
-    pub seed: Option<Vec<u8>>,
-    pub randomness: Option<[u8; 32]>,
-    pub proof: Option<[u8; 64]>,
+    /// Raw ed25519 public key, used as the curve point in ECVRF verification.
     pub public_key: [u8; 32],
+    /// The oracle's signing identity, checked against the `FulfillRandomness`
+    /// signer. Same underlying bytes as `public_key` (a Solana `Pubkey` is an
+    /// ed25519 public key), kept as its own field since the two are used for
+    /// different things: signer comparisons here, curve arithmetic there.
+    pub oracle_authority: Pubkey,
+    /// The paired game account this VRF account's rounds feed into. Recorded
+    /// at `Initialize` time so an oracle crank can discover the pairing by
+    /// scanning VRF accounts alone, without an out-of-band account list.
+    pub game_account: Pubkey,
+    /// Index of the next round the oracle should fulfill; rounds are always
+    /// fulfilled in order, so this also bounds replay of old proofs.
+    pub counter: u64,
+    pub rounds: Vec<VrfRound>,
+}
+
+impl VrfAccountState {
+    /// Worst-case Borsh size for `MAX_ROUNDS` fully-populated rounds, each
+    /// with a 32-byte seed and its largest possible condition
+    /// (`AccountData`: 1-byte tag + 32-byte pubkey + 32-byte program_id +
+    /// 32-byte hash).
+    const ROUND_LEN: usize = 4 + 32 // seed: Vec<u8> len prefix + bytes
+        + (1 + 32) // randomness: Option<[u8; 32]>
+        + (1 + 80) // proof: Option<[u8; 80]>
+        + (1 + 1 + 32 + 32 + 32); // condition: Option<WitnessCondition>
+
+    pub const LEN: usize = 32 // authority
+        + 1 // is_initialized
+        + 32 // public_key
+        + 32 // oracle_authority
+        + 32 // game_account
+        + 8 // counter
+        + 4 // rounds: Vec length prefix
+        + Self::ROUND_LEN * MAX_ROUNDS;
+}
+
+/// One game round: the player's blinded commitment, the randomness copied
+/// over once fulfilled, and the revealed guess/result.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct GameRound {
+    /// `SHA3_256(guess‖player_pubkey‖blinding)`, recorded by `CommitGuess`
+    /// before randomness is requested so the player can't tailor their
+    /// guess to a known winning number.
+    pub commitment: Option<[u8; 32]>,
+    pub randomness: Option<[u8; 32]>,
+    pub player_guess: Option<u8>,
+    pub result: Option<GameResult>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct GameAccountState {
     pub authority: Pubkey,
     pub is_initialized: bool,
-    pub randomness: Option<[u8; 32]>,
+    /// Status of the round at `current_round`.
     pub game_state: GameState,
-    pub player_guess: Option<u8>,
-    pub result: Option<GameResult>,
+    /// Index into `rounds` that `game_state` describes and that
+    /// `SubmitGuess` must target next.
+    pub current_round: u8,
+    pub rounds: Vec<GameRound>,
+}
+
+impl GameAccountState {
+    const ROUND_LEN: usize = (1 + 32) // commitment: Option<[u8; 32]>
+        + (1 + 32) // randomness: Option<[u8; 32]>
+        + (1 + 1) // player_guess: Option<u8>
+        + (1 + 1); // result: Option<GameResult>
+
+    pub const LEN: usize = 32 // authority
+        + 1 // is_initialized
+        + 1 // game_state: enum tag
+        + 1 // current_round
+        + 4 // rounds: Vec length prefix
+        + Self::ROUND_LEN * MAX_ROUNDS;
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
 pub enum GameState {
+    AwaitingCommitment,
     AwaitingRandomness,
     AwaitingPlayerGuess,
     GameComplete,
@@ -38,4 +131,4 @@ pub enum GameResult {
 pub fn calculate_game_number(randomness: &[u8; 32]) -> u8 {
     // Use first byte of randomness modulo 100 to get a number between 0-99
     randomness[0] % 100
-} 
\ No newline at end of file
+}
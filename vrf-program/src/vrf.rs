@@ -0,0 +1,196 @@
+//! ECVRF-EDWARDS25519-SHA512-TAI (RFC 9381) prove/verify.
+//!
+//! `processor.rs` treats VRF proofs as opaque byte blobs; this module is the
+//! only place that touches curve arithmetic. A proof `pi = (Gamma || c || s)`
+//! is 80 bytes: a compressed Edwards point, a 16-byte challenge, and a
+//! 32-byte scalar.
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+
+use crate::error::VrfError;
+
+/// Suite string for ECVRF-EDWARDS25519-SHA512-TAI, per RFC 9381 Appendix A.
+const SUITE: u8 = 0x03;
+const MAX_HASH_TO_CURVE_ATTEMPTS: u16 = 256;
+
+pub struct Vrf;
+
+impl Vrf {
+    /// Produce a proof over `alpha` for the key derived from `secret_key_seed`,
+    /// the same 32-byte seed an ed25519 `Keypair`'s secret half is built from.
+    pub fn prove(secret_key_seed: &[u8; 32], alpha: &[u8]) -> Result<[u8; 80], VrfError> {
+        let (x, public_key) = expand_secret(secret_key_seed);
+
+        let h_point = hash_to_curve(&public_key, alpha)?;
+        let gamma = x * h_point;
+
+        let k = nonce_scalar(secret_key_seed, &h_point);
+        let u = k * ED25519_BASEPOINT_POINT;
+        let v = k * h_point;
+
+        let c = challenge(&h_point, &gamma, &u, &v);
+        let c_scalar = scalar_from_c(&c);
+        let s = k + c_scalar * x;
+
+        let mut proof = [0u8; 80];
+        proof[0..32].copy_from_slice(gamma.compress().as_bytes());
+        proof[32..48].copy_from_slice(&c);
+        proof[48..80].copy_from_slice(s.as_bytes());
+        Ok(proof)
+    }
+
+    /// Verify `pi` against `public_key` and `alpha`, returning `beta`
+    /// (the 32-byte VRF output) on success.
+    pub fn verify(public_key: &[u8; 32], alpha: &[u8], pi: &[u8; 80]) -> Result<[u8; 32], VrfError> {
+        let y_point = CompressedEdwardsY(*public_key)
+            .decompress()
+            .ok_or(VrfError::InvalidProof)?;
+
+        let gamma = CompressedEdwardsY::from_slice(&pi[0..32])
+            .decompress()
+            .ok_or(VrfError::InvalidProof)?;
+        let mut c = [0u8; 16];
+        c.copy_from_slice(&pi[32..48]);
+        let c_scalar = scalar_from_c(&c);
+        let s_scalar = Scalar::from_canonical_bytes(pi[48..80].try_into().unwrap())
+            .ok_or(VrfError::InvalidProof)?;
+
+        let h_point = hash_to_curve(public_key, alpha)?;
+
+        let u = s_scalar * ED25519_BASEPOINT_POINT - c_scalar * y_point;
+        let v = s_scalar * h_point - c_scalar * gamma;
+
+        let c_prime = challenge(&h_point, &gamma, &u, &v);
+        if c_prime != c {
+            return Err(VrfError::InvalidProof);
+        }
+
+        Ok(proof_to_hash(&gamma))
+    }
+}
+
+/// Clamp `seed` into an ed25519 scalar and its public point, the standard
+/// RFC 8032 key-expansion step.
+fn expand_secret(seed: &[u8; 32]) -> (Scalar, [u8; 32]) {
+    let mut hasher = Sha512::new();
+    hasher.update(seed);
+    let hash = hasher.finalize();
+
+    let mut clamped = [0u8; 32];
+    clamped.copy_from_slice(&hash[0..32]);
+    clamped[0] &= 248;
+    clamped[31] &= 127;
+    clamped[31] |= 64;
+
+    let x = Scalar::from_bits(clamped);
+    let public_key = (x * ED25519_BASEPOINT_POINT).compress().to_bytes();
+    (x, public_key)
+}
+
+/// Deterministic per-proof nonce, derived from the secret seed and `H` so
+/// `prove` never needs a system RNG (unavailable on-chain anyway).
+fn nonce_scalar(secret_key_seed: &[u8; 32], h_point: &EdwardsPoint) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(secret_key_seed);
+    hasher.update(h_point.compress().as_bytes());
+    let hash = hasher.finalize();
+    Scalar::from_bytes_mod_order_wide(&hash.into())
+}
+
+/// `hash_to_curve` via try-and-increment: hash
+/// `suite‖0x01‖Y‖alpha‖ctr`, decode the first 32 bytes as a compressed
+/// Edwards point, and bump `ctr` until a valid one is found. The result is
+/// cleared to the prime-order subgroup.
+fn hash_to_curve(public_key: &[u8; 32], alpha: &[u8]) -> Result<EdwardsPoint, VrfError> {
+    for ctr in 0..MAX_HASH_TO_CURVE_ATTEMPTS {
+        let mut hasher = Sha512::new();
+        hasher.update([SUITE, 0x01]);
+        hasher.update(public_key);
+        hasher.update(alpha);
+        hasher.update([ctr as u8]);
+        let hash = hasher.finalize();
+
+        let mut candidate = [0u8; 32];
+        candidate.copy_from_slice(&hash[0..32]);
+        if let Some(point) = CompressedEdwardsY(candidate).decompress() {
+            return Ok(point.mul_by_cofactor());
+        }
+    }
+    Err(VrfError::InvalidProof)
+}
+
+/// `c' = SHA512(suite‖0x02‖H‖Gamma‖U‖V)[0..16]`.
+fn challenge(h_point: &EdwardsPoint, gamma: &EdwardsPoint, u: &EdwardsPoint, v: &EdwardsPoint) -> [u8; 16] {
+    let mut hasher = Sha512::new();
+    hasher.update([SUITE, 0x02]);
+    hasher.update(h_point.compress().as_bytes());
+    hasher.update(gamma.compress().as_bytes());
+    hasher.update(u.compress().as_bytes());
+    hasher.update(v.compress().as_bytes());
+    let hash = hasher.finalize();
+    let mut out = [0u8; 16];
+    out.copy_from_slice(&hash[0..16]);
+    out
+}
+
+/// `beta = SHA512(suite‖0x03‖(cofactor·Gamma))[0..32]`.
+fn proof_to_hash(gamma: &EdwardsPoint) -> [u8; 32] {
+    let mut hasher = Sha512::new();
+    hasher.update([SUITE, 0x03]);
+    hasher.update(gamma.mul_by_cofactor().compress().as_bytes());
+    let hash = hasher.finalize();
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hash[0..32]);
+    out
+}
+
+/// Widen the 16-byte challenge into a little-endian scalar.
+fn scalar_from_c(c: &[u8; 16]) -> Scalar {
+    let mut wide = [0u8; 32];
+    wide[0..16].copy_from_slice(c);
+    Scalar::from_bits(wide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prove_then_verify_round_trips() {
+        let secret_key_seed = [7u8; 32];
+        let (_, public_key) = expand_secret(&secret_key_seed);
+        let alpha = b"round one seed";
+
+        let proof = Vrf::prove(&secret_key_seed, alpha).expect("prove should succeed");
+        let beta = Vrf::verify(&public_key, alpha, &proof).expect("verify should accept a valid proof");
+
+        // Verifying again must be deterministic.
+        assert_eq!(beta, Vrf::verify(&public_key, alpha, &proof).unwrap());
+    }
+
+    #[test]
+    fn verify_rejects_a_bit_flipped_proof() {
+        let secret_key_seed = [7u8; 32];
+        let (_, public_key) = expand_secret(&secret_key_seed);
+        let alpha = b"round one seed";
+
+        let mut proof = Vrf::prove(&secret_key_seed, alpha).expect("prove should succeed");
+        proof[0] ^= 0x01;
+
+        assert!(Vrf::verify(&public_key, alpha, &proof).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_a_proof_for_the_wrong_public_key() {
+        let secret_key_seed = [7u8; 32];
+        let (_, other_public_key) = expand_secret(&[9u8; 32]);
+        let alpha = b"round one seed";
+
+        let proof = Vrf::prove(&secret_key_seed, alpha).expect("prove should succeed");
+
+        assert!(Vrf::verify(&other_public_key, alpha, &proof).is_err());
+    }
+}